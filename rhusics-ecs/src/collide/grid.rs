@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+use cgmath::{BaseFloat, Point2, Point3};
+use collision::prelude::*;
+
+use core::BroadPhase;
+
+/// Converts a bound's corner point into the integer grid cell coordinate it falls in.
+///
+/// Implemented for both `Point2` and `Point3` so `GridBroadPhase` works the same way in 2D and
+/// 3D; the cell coordinate is a small `Vec<i64>` rather than a fixed-size tuple so the rest of
+/// `GridBroadPhase` doesn't need to be generic over dimensionality.
+pub trait GridPoint {
+    /// Cell coordinate for this point, given `1. / cell_size`.
+    fn cell_coords(&self, inv_cell_size: f64) -> Vec<i64>;
+}
+
+impl<S: BaseFloat> GridPoint for Point2<S> {
+    fn cell_coords(&self, inv_cell_size: f64) -> Vec<i64> {
+        vec![
+            (self.x.to_f64().unwrap() * inv_cell_size).floor() as i64,
+            (self.y.to_f64().unwrap() * inv_cell_size).floor() as i64,
+        ]
+    }
+}
+
+impl<S: BaseFloat> GridPoint for Point3<S> {
+    fn cell_coords(&self, inv_cell_size: f64) -> Vec<i64> {
+        vec![
+            (self.x.to_f64().unwrap() * inv_cell_size).floor() as i64,
+            (self.y.to_f64().unwrap() * inv_cell_size).floor() as i64,
+            (self.z.to_f64().unwrap() * inv_cell_size).floor() as i64,
+        ]
+    }
+}
+
+/// Uniform spatial-hash grid broad phase, following Hedgewars' grid-based collision approach.
+///
+/// Cheaper than the default DBVT broad phase for scenes with many similarly sized, roughly
+/// uniformly distributed bodies: `O(n)` to rasterize plus work per occupied cell proportional to
+/// the number of pairs of bodies sharing it, instead of `O(m log^2 n)`.
+///
+/// Each call to [`find_potentials`](../../core/trait.BroadPhase.html#tymethod.find_potentials)
+/// clears the grid, rasterizes every object's bound into the cell range it overlaps, and produces
+/// candidate pairs from objects that share a cell, with pairs that co-occupy more than one cell
+/// deduplicated so they are only reported once.
+pub struct GridBroadPhase<D> {
+    cell_size: f64,
+    cells: HashMap<Vec<i64>, Vec<usize>>,
+    marker: PhantomData<D>,
+}
+
+impl<D> GridBroadPhase<D> {
+    /// Create a new grid broad phase with the given cell size.
+    ///
+    /// Pick a cell size close to the typical size of the bodies in the scene; too small and most
+    /// bodies span many cells, too large and most cells contain most bodies.
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<D> BroadPhase<D> for GridBroadPhase<D>
+where
+    D: HasBound,
+    <D as HasBound>::Bound: Bound,
+    <<D as HasBound>::Bound as Bound>::Point: GridPoint,
+{
+    fn find_potentials(&mut self, values: &mut Vec<D>) -> Vec<(usize, usize)> {
+        self.cells.clear();
+        let inv_cell_size = 1. / self.cell_size;
+
+        for (i, value) in values.iter().enumerate() {
+            let bound = value.bound();
+            for cell in cell_range(
+                &bound.min().cell_coords(inv_cell_size),
+                &bound.max().cell_coords(inv_cell_size),
+            ) {
+                self.cells.entry(cell).or_insert_with(Vec::new).push(i);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut pairs = Vec::new();
+        for occupants in self.cells.values() {
+            for a in 0..occupants.len() {
+                for b in (a + 1)..occupants.len() {
+                    let pair = if occupants[a] < occupants[b] {
+                        (occupants[a], occupants[b])
+                    } else {
+                        (occupants[b], occupants[a])
+                    };
+                    if seen.insert(pair) {
+                        pairs.push(pair);
+                    }
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Cartesian product of the inclusive per-axis ranges `min[i]..=max[i]`.
+fn cell_range(min: &[i64], max: &[i64]) -> Vec<Vec<i64>> {
+    let mut result = vec![Vec::new()];
+    for (&lo, &hi) in min.iter().zip(max.iter()) {
+        let mut next = Vec::with_capacity(result.len() * (hi - lo + 1).max(0) as usize);
+        for prefix in &result {
+            for v in lo..=hi {
+                let mut cell = prefix.clone();
+                cell.push(v);
+                next.push(cell);
+            }
+        }
+        result = next;
+    }
+    result
+}
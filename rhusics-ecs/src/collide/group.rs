@@ -0,0 +1,114 @@
+use specs::prelude::{Component, DenseVecStorage};
+
+/// All groups, used as the default membership/whitelist so an object with no `CollisionGroups`
+/// (or a freshly constructed one) collides with everything, same as before groups existed.
+const ALL_GROUPS: u32 = ::std::u32::MAX;
+
+/// Collision group/layer filter, modelled on ncollide's membership/whitelist/blacklist scheme.
+///
+/// Attach alongside a `CollisionShape` to restrict what it is allowed to collide with. Two shapes
+/// `a` and `b` are allowed to interact only if both directions of the check pass, see
+/// [`collides_with`](fn.collides_with.html).
+///
+/// Each mask is a `u32`, giving up to 32 distinct groups. The default is a member of every group
+/// and whitelists every group, so attaching a default-constructed `CollisionGroups` (or omitting
+/// the component entirely) doesn't change existing collision behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionGroups {
+    membership: u32,
+    whitelist: u32,
+    blacklist: u32,
+}
+
+impl Default for CollisionGroups {
+    fn default() -> Self {
+        Self {
+            membership: ALL_GROUPS,
+            whitelist: ALL_GROUPS,
+            blacklist: 0,
+        }
+    }
+}
+
+impl CollisionGroups {
+    /// Create a new `CollisionGroups`, a member of every group, with a whitelist/blacklist that
+    /// allows collision with everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the groups this object is a member of.
+    pub fn with_membership(mut self, membership: u32) -> Self {
+        self.membership = membership;
+        self
+    }
+
+    /// Set the groups this object is allowed to collide with.
+    pub fn with_whitelist(mut self, whitelist: u32) -> Self {
+        self.whitelist = whitelist;
+        self
+    }
+
+    /// Set the groups this object will never collide with, regardless of whitelist.
+    pub fn with_blacklist(mut self, blacklist: u32) -> Self {
+        self.blacklist = blacklist;
+        self
+    }
+}
+
+impl Component for CollisionGroups {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Check whether two `CollisionGroups` are allowed to interact.
+///
+/// Both directions of the membership/whitelist/blacklist test must pass:
+///
+/// ```text
+/// (a.membership & b.whitelist) != 0 && (b.membership & a.whitelist) != 0
+///     && (a.membership & b.blacklist) == 0 && (b.membership & a.blacklist) == 0
+/// ```
+pub fn collides_with(a: &CollisionGroups, b: &CollisionGroups) -> bool {
+    (a.membership & b.whitelist) != 0 && (b.membership & a.whitelist) != 0
+        && (a.membership & b.blacklist) == 0 && (b.membership & a.blacklist) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_groups_collide_with_each_other() {
+        let a = CollisionGroups::new().with_membership(0b0001);
+        let b = CollisionGroups::new().with_membership(0b0010);
+        assert!(collides_with(&a, &b));
+    }
+
+    #[test]
+    fn untouched_groups_collide_with_each_other() {
+        // Entities that never call `with_membership` (or that don't carry a `CollisionGroups`
+        // at all, falling back to `CollisionGroups::default()` in `spatial_collision.rs`) must
+        // keep colliding with everything, matching behavior from before groups existed.
+        let a = CollisionGroups::new();
+        let b = CollisionGroups::new();
+        assert!(collides_with(&a, &b));
+    }
+
+    #[test]
+    fn blacklist_overrides_whitelist() {
+        let a = CollisionGroups::new()
+            .with_membership(0b0001)
+            .with_blacklist(0b0010);
+        let b = CollisionGroups::new().with_membership(0b0010);
+        assert!(!collides_with(&a, &b));
+    }
+
+    #[test]
+    fn whitelist_restricts_membership() {
+        let a = CollisionGroups::new()
+            .with_membership(0b0001)
+            .with_whitelist(0b0100);
+        let b = CollisionGroups::new().with_membership(0b0010);
+        assert!(!collides_with(&a, &b));
+    }
+}
@@ -0,0 +1,41 @@
+use collision::Contact;
+use specs::prelude::Entity;
+
+use core::Primitive;
+
+/// User-supplied hooks invoked by `SpatialCollisionSystem` during narrow-phase resolution.
+///
+/// Where `CollisionGroups` only supports static, symmetric group masks, `ContactHooks` lets game
+/// logic veto or tweak individual contacts based on runtime state, e.g. one-way platforms that
+/// only collide when approached from above, or conditional pass-through based on entity state.
+///
+/// Both methods default to accepting the contact unchanged, so implementing only the one that's
+/// needed is enough.
+pub trait ContactHooks<P>: Send + Sync
+where
+    P: Primitive,
+{
+    /// Called for every pair narrow phase is about to be run on, before it runs. Returning
+    /// `false` drops the pair before the narrow phase algorithm sees it, the same as a
+    /// `CollisionGroups` rejection, so the pair never reaches game code and never pays for
+    /// manifold generation.
+    fn filter_contact_pair(&self, _a: Entity, _b: Entity) -> bool {
+        true
+    }
+
+    /// Called for every manifold point after narrow phase. Mutate `contact` to tweak it, or
+    /// return `false` to discard this point.
+    fn modify_contact(&self, _contact: &mut Contact<P::Point>, _a: Entity, _b: Entity) -> bool {
+        true
+    }
+}
+
+/// The default hook set: accepts every pair and every contact unchanged.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoOpContactHooks;
+
+impl<P> ContactHooks<P> for NoOpContactHooks
+where
+    P: Primitive,
+{
+}
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use cgmath::prelude::*;
+use collision::dbvt::{DynamicBoundingVolumeTree, TreeValue, Visitor};
+use collision::prelude::*;
+use specs::prelude::{Component, Entities, Entity, Join, NullStorage, ReadStorage};
+
+use core::{CollisionShape, GetId, Primitive};
+
+/// Marker component flagging a `CollisionShape` as a sensor.
+///
+/// Sensors take part in broad and narrow phase overlap testing like any other shape, but never
+/// produce a `ContactEvent` with a full contact manifold. Instead `SpatialCollisionSystem` emits
+/// `ProximityEvent`s for them, decided by a boolean bounds intersection test, which is the right
+/// primitive for trigger volumes such as doors, pickups or damage zones. Sensor pairs that involve
+/// a dirty pose still go through the configured narrow phase inside `tree_collide`; the contacts
+/// it produces for them are discarded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sensor;
+
+impl Component for Sensor {
+    type Storage = NullStorage<Self>;
+}
+
+/// Proximity event, emitted by `SpatialCollisionSystem` for pairs involving a `Sensor` shape.
+///
+/// Unlike `ContactEvent`, no contact manifold is carried, only the fact that the pair started or
+/// stopped overlapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProximityEvent<I> {
+    /// The pair started overlapping this frame.
+    Started(I, I),
+    /// The pair, previously overlapping, stopped doing so this frame.
+    Stopped(I, I),
+}
+
+impl<I> ProximityEvent<I> {
+    /// The two entities involved in the event.
+    pub fn bodies(&self) -> (&I, &I) {
+        match *self {
+            ProximityEvent::Started(ref a, ref b) | ProximityEvent::Stopped(ref a, ref b) => {
+                (a, b)
+            }
+        }
+    }
+}
+
+/// Order a pair of entities by id, so a pair can be used as a key regardless of which order it
+/// was discovered in.
+pub(crate) fn ordered_pair(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a.id() <= b.id() {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Compute the full set of currently-overlapping sensor pairs.
+///
+/// `tree_collide` is incremental: it only reports contacts for pairs that involve a pose that's
+/// dirty *this frame*, so it cannot be used to tell whether a sensor and a stationary body are
+/// still overlapping. This instead re-queries the broad phase for every `Sensor` every frame and
+/// decides overlap with a boolean `Discrete` test between the two shapes' bounds, which is all a
+/// trigger volume needs. Unlike the full narrow phase (`NarrowPhase::collide`), this function never
+/// builds a contact manifold. It does not keep sensor pairs out of `tree_collide`, whose narrow
+/// phase still runs on sensor pairs involving a dirty pose.
+pub fn current_sensor_overlaps<P, T, B, Y, D>(
+    tree: &DynamicBoundingVolumeTree<D>,
+    entities: &Entities,
+    shapes: &ReadStorage<CollisionShape<P, T, B, Y>>,
+    sensors: &ReadStorage<Sensor>,
+) -> HashSet<(Entity, Entity)>
+where
+    P: Primitive + Send + Sync + 'static,
+    P::Point: Debug + Send + Sync + 'static,
+    <P::Point as EuclideanSpace>::Scalar: Send + Sync + 'static,
+    <P::Point as EuclideanSpace>::Diff: Debug + Send + Sync + 'static,
+    T: Component + Transform<P::Point> + Send + Sync + Clone + 'static,
+    Y: Default + Send + Sync + 'static,
+    B: Bound<Point = P::Point> + Discrete<B> + Send + Sync + 'static,
+    D: TreeValue<Bound = B> + HasBound<Bound = B> + GetId<Entity>,
+{
+    let mut overlapping = HashSet::new();
+    for (a, _, shape_a) in (&**entities, sensors, shapes).join() {
+        let mut visitor = BoundVisitor {
+            bound: shape_a.bound.clone(),
+        };
+        for (data, ()) in tree.query(&mut visitor) {
+            let b = data.get_id();
+            if a == b || overlapping.contains(&ordered_pair(a, b)) {
+                continue;
+            }
+            if let Some(shape_b) = shapes.get(b) {
+                if shape_a.bound.intersects(&shape_b.bound) {
+                    overlapping.insert(ordered_pair(a, b));
+                }
+            }
+        }
+    }
+    overlapping
+}
+
+struct BoundVisitor<B> {
+    bound: B,
+}
+
+impl<B> Visitor for BoundVisitor<B>
+where
+    B: Discrete<B>,
+{
+    type Bound = B;
+    type Result = ();
+
+    fn accept(&mut self, bound: &B) -> Option<()> {
+        if self.bound.intersects(bound) {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
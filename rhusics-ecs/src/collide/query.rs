@@ -0,0 +1,173 @@
+use std::fmt::Debug;
+
+use cgmath::prelude::*;
+use cgmath::BaseFloat;
+use collision::dbvt::{DynamicBoundingVolumeTree, TreeValue, Visitor};
+use collision::prelude::*;
+use collision::Ray;
+use specs::prelude::{Entity, ReadStorage};
+
+use core::{CollisionShape, GetId, Primitive};
+
+/// Result of a [`raycast`](fn.raycast.html) query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit<S, P> {
+    /// The entity that was hit
+    pub entity: Entity,
+    /// Time of impact: `point == ray.origin + toi * ray.direction`. Correct regardless of whether
+    /// `ray.direction` is unit length, see [`toi_of`](fn.toi_of.html).
+    pub toi: S,
+    /// World space point where the ray hit the entity's shape
+    pub point: P,
+}
+
+/// Distance from the ray's origin to `point`, in units of `ray.direction`'s length, i.e. `t` such
+/// that `point == ray.origin + t * ray.direction`. Only meaningful for points that lie on the ray.
+fn toi_of<S, P, D>(ray: &Ray<S, P, D>, point: P) -> S
+where
+    P: EuclideanSpace<Scalar = S, Diff = D>,
+    S: BaseFloat,
+    D: InnerSpace<Scalar = S>,
+{
+    (point - ray.origin).magnitude() / ray.direction.magnitude()
+}
+
+/// Cast a ray against the shapes tracked by `tree`, returning the closest hit, if any.
+///
+/// Descends the DBVT, pruning subtrees whose bound the ray misses, then does an exact
+/// ray/primitive test against each surviving candidate's `Primitive` in its local space and keeps
+/// the closest hit. Subtrees are not pruned by the best hit found so far: every leaf whose bound
+/// the ray intersects gets the exact test.
+pub fn raycast<P, T, B, Y, D>(
+    tree: &DynamicBoundingVolumeTree<D>,
+    shapes: &ReadStorage<CollisionShape<P, T, B, Y>>,
+    poses: &ReadStorage<T>,
+    ray: &Ray<<P::Point as EuclideanSpace>::Scalar, P::Point, <P::Point as EuclideanSpace>::Diff>,
+) -> Option<RayHit<<P::Point as EuclideanSpace>::Scalar, P::Point>>
+where
+    P: Primitive
+        + Continuous<
+            Ray<<P::Point as EuclideanSpace>::Scalar, P::Point, <P::Point as EuclideanSpace>::Diff>,
+            Result = Option<P::Point>,
+        >,
+    P::Point: EuclideanSpace + Debug,
+    <P::Point as EuclideanSpace>::Scalar: BaseFloat,
+    <P::Point as EuclideanSpace>::Diff: InnerSpace + Debug,
+    B: Bound<Point = P::Point>
+        + Continuous<
+            Ray<<P::Point as EuclideanSpace>::Scalar, P::Point, <P::Point as EuclideanSpace>::Diff>,
+            Result = Option<P::Point>,
+        >,
+    T: Transform<P::Point>,
+    Y: Send + Sync + 'static,
+    D: TreeValue<Bound = B> + GetId<Entity>,
+{
+    let mut visitor = RayVisitor { ray };
+    let mut best: Option<RayHit<<P::Point as EuclideanSpace>::Scalar, P::Point>> = None;
+
+    for (data, _) in tree.query(&mut visitor) {
+        let entity = data.get_id();
+        let (shape, pose) = match (shapes.get(entity), poses.get(entity)) {
+            (Some(shape), Some(pose)) => (shape, pose),
+            _ => continue,
+        };
+        let inverse = match pose.inverse_transform() {
+            Some(inverse) => inverse,
+            None => continue,
+        };
+        let local_ray = ray.transform(&inverse);
+        if let Some(local_point) = shape.primitive.intersection(&local_ray) {
+            let point = pose.transform_point(local_point);
+            let toi = toi_of(ray, point);
+            if best.as_ref().map(|hit| toi < hit.toi).unwrap_or(true) {
+                best = Some(RayHit { entity, toi, point });
+            }
+        }
+    }
+
+    best
+}
+
+/// Find every entity whose shape, tracked by `tree`, contains `point`.
+pub fn point_query<P, T, B, Y, D>(
+    tree: &DynamicBoundingVolumeTree<D>,
+    shapes: &ReadStorage<CollisionShape<P, T, B, Y>>,
+    poses: &ReadStorage<T>,
+    point: P::Point,
+) -> Vec<Entity>
+where
+    P: Primitive + Contains<P::Point>,
+    P::Point: EuclideanSpace + Debug,
+    <P::Point as EuclideanSpace>::Scalar: BaseFloat,
+    B: Bound<Point = P::Point> + Contains<P::Point>,
+    T: Transform<P::Point>,
+    Y: Send + Sync + 'static,
+    D: TreeValue<Bound = B> + GetId<Entity>,
+{
+    let mut visitor = PointVisitor { point };
+    tree.query(&mut visitor)
+        .into_iter()
+        .filter_map(|(data, _)| {
+            let entity = data.get_id();
+            let shape = shapes.get(entity)?;
+            let pose = poses.get(entity)?;
+            let local_point = pose.inverse_transform()?.transform_point(point);
+            if shape.primitive.contains(&local_point) {
+                Some(entity)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+struct RayVisitor<'a, S, P, D>
+where
+    P: EuclideanSpace<Scalar = S> + 'a,
+    S: BaseFloat + 'a,
+    D: 'a,
+{
+    ray: &'a Ray<S, P, D>,
+}
+
+impl<'a, S, P, D, B> Visitor for RayVisitor<'a, S, P, D>
+where
+    P: EuclideanSpace<Scalar = S, Diff = D>,
+    S: BaseFloat,
+    D: InnerSpace<Scalar = S>,
+    B: Bound<Point = P> + Continuous<Ray<S, P, D>, Result = Option<P>>,
+{
+    type Bound = B;
+    type Result = S;
+
+    // Accept every leaf whose *bound* the ray intersects; `raycast` then runs the exact
+    // primitive test on each and keeps the closest real hit. We previously tried to prune on a
+    // `best_toi` tightened from bound-entry distances, but a bound-entry toi is not a confirmed
+    // hit: a nearer bound can be missed entirely by its primitive while a farther bound holds the
+    // only real intersection, so pruning on it can skip or misrank the correct answer. Doing the
+    // real pruning would require running the exact primitive test during descent and feeding a
+    // real best-hit toi back into the visitor, which the `Visitor` API here doesn't support.
+    fn accept(&mut self, bound: &B) -> Option<S> {
+        bound.intersection(self.ray).map(|point| toi_of(self.ray, point))
+    }
+}
+
+struct PointVisitor<P> {
+    point: P,
+}
+
+impl<P, B> Visitor for PointVisitor<P>
+where
+    B: Contains<P>,
+{
+    type Bound = B;
+    type Result = ();
+
+    fn accept(&mut self, bound: &B) -> Option<()> {
+        if bound.contains(&self.point) {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
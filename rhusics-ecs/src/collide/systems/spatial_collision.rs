@@ -1,9 +1,12 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
 
 use cgmath::BaseFloat;
 use cgmath::prelude::*;
 use collision::dbvt::{DynamicBoundingVolumeTree, TreeValue};
 use collision::prelude::*;
+use collision::Contact;
 use shrev::EventChannel;
 use specs::prelude::{BitSet, Component, Entities, Entity, InsertedFlag, Join, ModifiedFlag,
                      ReadStorage, ReaderId, Resources, System, Tracked, Write};
@@ -11,6 +14,10 @@ use specs::prelude::{BitSet, Component, Entities, Entity, InsertedFlag, Join, Mo
 use core::{tree_collide, BroadPhase, CollisionData, CollisionShape, ContactEvent, GetId,
            NarrowPhase, NextFrame, Primitive};
 
+use collide::group::{collides_with, CollisionGroups};
+use collide::hooks::{ContactHooks, NoOpContactHooks};
+use collide::proximity::{current_sensor_overlaps, ProximityEvent, Sensor};
+
 /// Collision detection [system](https://docs.rs/specs/0.9.5/specs/trait.System.html) for use with
 /// [`specs`](https://docs.rs/specs/0.9.5/specs/).
 ///
@@ -21,6 +28,25 @@ use core::{tree_collide, BroadPhase, CollisionData, CollisionShape, ContactEvent
 /// broad phase that has complexity O(m log^2 n), where m is the number of shapes that have a dirty
 /// pose.
 ///
+/// Pairs whose `CollisionGroups` don't allow them to interact (see
+/// [`collides_with`](../group/fn.collides_with.html)), or that a
+/// [`ContactHooks`](../hooks/trait.ContactHooks.html)'s `filter_contact_pair` rejects, never reach
+/// narrow phase in the first place: the narrow phase given to
+/// [`with_narrow_phase`](#method.with_narrow_phase) is installed behind a wrapper that checks
+/// both before calling through, so a rejected pair doesn't pay for manifold generation. A hook can
+/// additionally veto or adjust individual contacts that static group masks can't express via
+/// `modify_contact`, which still runs after narrow phase, since it mutates a manifold that has to
+/// exist first; see [`with_hooks`](#method.with_hooks).
+///
+/// Pairs where either shape carries the `Sensor` marker never produce a `ContactEvent`. Instead,
+/// every frame the system re-derives the full set of overlapping sensor pairs from a boolean
+/// bounds intersection test (see
+/// [`current_sensor_overlaps`](../proximity/fn.current_sensor_overlaps.html), which does not rely
+/// on `tree_collide`'s incremental, dirty-pose-only contact list) and emits
+/// `ProximityEvent::Started`/`Stopped` by diffing it against last frame's set. Sensor pairs are not
+/// excluded from `tree_collide`: narrow phase still runs on those involving a dirty pose, and the
+/// resulting contacts are dropped.
+///
 /// Can handle any transform component type, as long as the type implements
 /// [`Transform`](https://docs.rs/cgmath/0.15.0/cgmath/trait.Transform.html), and as long as the
 /// storage is wrapped in
@@ -35,7 +61,7 @@ use core::{tree_collide, BroadPhase, CollisionData, CollisionShape, ContactEvent
 ///
 /// ### System Function:
 ///
-/// `fn(Entities, T, NextFrame<T>, CollisionShape, DynamicBoundingVolumeTree<D>) -> (DynamicBoundingVolumeTree<D>, EventChannel<ContactEvent>)`
+/// `fn(Entities, T, NextFrame<T>, CollisionShape, CollisionGroups, Sensor, DynamicBoundingVolumeTree<D>) -> (DynamicBoundingVolumeTree<D>, EventChannel<ContactEvent>, EventChannel<ProximityEvent>)`
 pub struct SpatialCollisionSystem<P, T, D, B, Y = ()>
 where
     P: Primitive,
@@ -48,6 +74,56 @@ where
     pose_modified_id: Option<ReaderId<ModifiedFlag>>,
     next_pose_inserted_id: Option<ReaderId<InsertedFlag>>,
     next_pose_modified_id: Option<ReaderId<ModifiedFlag>>,
+    proximity_state: HashSet<(Entity, Entity)>,
+    hooks: Arc<RwLock<Box<ContactHooks<P>>>>,
+    groups: Arc<RwLock<HashMap<Entity, CollisionGroups>>>,
+}
+
+/// Wraps a `NarrowPhase` so that pairs rejected by `CollisionGroups` or
+/// `ContactHooks::filter_contact_pair` never reach the wrapped narrow phase, instead of being
+/// filtered out of its output afterwards.
+///
+/// `groups` and `hooks` are shared (via `Arc`) with the `SpatialCollisionSystem` that installed
+/// this wrapper: `groups` is refreshed from the `CollisionGroups` storage at the start of every
+/// `run`, and `hooks` is updated in place by `with_hooks`, since `tree_collide` only gives us
+/// access to entity ids here, not component storage or the system itself.
+///
+/// Generic only over the wrapped `N` and the shape primitive `P` (needed for `ContactHooks<P>`),
+/// not `T`/`B`/`Y`, so wrapping doesn't add any `'static` requirement on those beyond what `N`
+/// itself already needs.
+struct GroupFilteredNarrowPhase<P, N>
+where
+    P: Primitive,
+{
+    inner: N,
+    groups: Arc<RwLock<HashMap<Entity, CollisionGroups>>>,
+    hooks: Arc<RwLock<Box<ContactHooks<P>>>>,
+}
+
+impl<P, T, B, Y, N> NarrowPhase<P, T, B, Y> for GroupFilteredNarrowPhase<P, N>
+where
+    P: Primitive,
+    T: Transform<P::Point>,
+    N: NarrowPhase<P, T, B, Y>,
+{
+    fn collide(
+        &self,
+        left: (Entity, &T, &CollisionShape<P, T, B, Y>),
+        right: (Entity, &T, &CollisionShape<P, T, B, Y>),
+    ) -> Option<Contact<P::Point>> {
+        let groups = self.groups.read().unwrap();
+        if !collides_with(
+            &groups.get(&left.0).cloned().unwrap_or_default(),
+            &groups.get(&right.0).cloned().unwrap_or_default(),
+        ) {
+            return None;
+        }
+        drop(groups);
+        if !self.hooks.read().unwrap().filter_contact_pair(left.0, right.0) {
+            return None;
+        }
+        self.inner.collide(left, right)
+    }
 }
 
 impl<P, T, D, B, Y> SpatialCollisionSystem<P, T, D, B, Y>
@@ -77,12 +153,23 @@ where
             pose_modified_id: None,
             next_pose_inserted_id: None,
             next_pose_modified_id: None,
+            proximity_state: HashSet::default(),
+            hooks: Arc::new(RwLock::new(Box::new(NoOpContactHooks))),
+            groups: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     /// Specify what narrow phase algorithm to use
+    ///
+    /// Installed behind a wrapper that checks `CollisionGroups` and
+    /// `ContactHooks::filter_contact_pair` first, so pairs either of those reject never reach
+    /// `narrow`, whichever order `with_narrow_phase` and `with_hooks` are called in.
     pub fn with_narrow_phase<N: NarrowPhase<P, T, B, Y> + 'static>(mut self, narrow: N) -> Self {
-        self.narrow = Some(Box::new(narrow));
+        self.narrow = Some(Box::new(GroupFilteredNarrowPhase {
+            inner: narrow,
+            groups: self.groups.clone(),
+            hooks: self.hooks.clone(),
+        }));
         self
     }
 
@@ -91,6 +178,17 @@ where
         self.broad = Some(Box::new(broad));
         self
     }
+
+    /// Specify contact hooks to filter and/or modify contacts during narrow phase resolution.
+    ///
+    /// `filter_contact_pair` is consulted by the same wrapper that enforces `CollisionGroups`,
+    /// before narrow phase runs; `modify_contact` still runs afterwards, since it mutates a
+    /// contact that has to exist first. Defaults to `NoOpContactHooks`, which accepts everything
+    /// unchanged.
+    pub fn with_hooks<H: ContactHooks<P> + 'static>(mut self, hooks: H) -> Self {
+        *self.hooks.write().unwrap() = Box::new(hooks);
+        self
+    }
 }
 
 impl<'a, P, T, Y, B, D> System<'a> for SpatialCollisionSystem<P, T, (usize, D), B, Y>
@@ -120,12 +218,25 @@ where
         ReadStorage<'a, T>,
         ReadStorage<'a, NextFrame<T>>,
         ReadStorage<'a, CollisionShape<P, T, B, Y>>,
+        ReadStorage<'a, CollisionGroups>,
+        ReadStorage<'a, Sensor>,
         Write<'a, EventChannel<ContactEvent<Entity, P::Point>>>,
+        Write<'a, EventChannel<ProximityEvent<Entity>>>,
         Write<'a, DynamicBoundingVolumeTree<D>>,
     );
 
     fn run(&mut self, system_data: Self::SystemData) {
-        let (entities, poses, next_poses, shapes, mut event_channel, mut tree) = system_data;
+        let (
+            entities,
+            poses,
+            next_poses,
+            shapes,
+            groups,
+            sensors,
+            mut event_channel,
+            mut proximity_channel,
+            mut tree,
+        ) = system_data;
         self.dirty.clear();
 
         poses.populate_inserted(self.pose_inserted_id.as_mut().unwrap(), &mut self.dirty);
@@ -139,7 +250,17 @@ where
             &mut self.dirty,
         );
 
-        event_channel.iter_write(tree_collide(
+        {
+            // Refreshed before `tree_collide` runs so `GroupFilteredNarrowPhase::collide` (which
+            // only has entity ids to go on) sees this frame's `CollisionGroups` assignments.
+            let mut cache = self.groups.write().unwrap();
+            cache.clear();
+            for (entity, group) in (&*entities, &groups).join() {
+                cache.insert(entity, group.clone());
+            }
+        }
+
+        let contacts = tree_collide(
             &SpatialCollisionData {
                 poses: &poses,
                 shapes: &shapes,
@@ -150,7 +271,48 @@ where
             &mut *tree,
             &mut self.broad,
             &self.narrow,
-        ));
+        );
+
+        let mut normal_contacts = Vec::with_capacity(contacts.len());
+        for mut contact in contacts {
+            let (a, b) = contact.bodies;
+            // CollisionGroups and ContactHooks::filter_contact_pair were already checked by
+            // GroupFilteredNarrowPhase before narrow phase ran, so a pair only gets here with
+            // tree_collide having already agreed it's allowed to interact. modify_contact still
+            // has to run here: it mutates the contact, which doesn't exist until now.
+            if !self.hooks
+                .read()
+                .unwrap()
+                .modify_contact(&mut contact.contact, a, b)
+            {
+                continue;
+            }
+            // Sensor pairs are handled separately below, from a full, frame-independent overlap
+            // query, since tree_collide only reports contacts touching a pose dirty this frame.
+            if sensors.get(a).is_some() || sensors.get(b).is_some() {
+                continue;
+            }
+            normal_contacts.push(contact);
+        }
+
+        let current_sensor_pairs = current_sensor_overlaps(&*tree, &entities, &shapes, &sensors);
+
+        for pair in &current_sensor_pairs {
+            if self.proximity_state.insert(*pair) {
+                proximity_channel.single_write(ProximityEvent::Started(pair.0, pair.1));
+            }
+        }
+        let ended: Vec<_> = self.proximity_state
+            .iter()
+            .filter(|pair| !current_sensor_pairs.contains(*pair))
+            .cloned()
+            .collect();
+        for pair in ended {
+            self.proximity_state.remove(&pair);
+            proximity_channel.single_write(ProximityEvent::Stopped(pair.0, pair.1));
+        }
+
+        event_channel.iter_write(normal_contacts);
     }
 
     fn setup(&mut self, res: &mut Resources) {
@@ -0,0 +1,158 @@
+use std::fmt::Debug;
+use std::marker;
+
+use cgmath::num_traits::NumCast;
+use cgmath::{BaseFloat, Basis2, EuclideanSpace, InnerSpace, Quaternion, Rotation, VectorSpace,
+             Zero};
+use core::{NextFrame, Pose};
+use specs::prelude::{Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System,
+                     WriteStorage};
+
+/// Blend factor resource, the fraction of the way through the current fixed physics step.
+///
+/// Supplied by the engine's fixed-timestep accumulator each render frame; always clamped to
+/// `[0, 1]` on construction by [`new`](#method.new).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterpolationTime(f64);
+
+impl Default for InterpolationTime {
+    fn default() -> Self {
+        InterpolationTime(0.)
+    }
+}
+
+impl InterpolationTime {
+    /// Create a new blend factor. Values outside `[0, 1]` are clamped.
+    pub fn new(alpha: f64) -> Self {
+        InterpolationTime(alpha.min(1.).max(0.))
+    }
+
+    /// The blend factor, always in `[0, 1]`.
+    pub fn alpha(&self) -> f64 {
+        self.0
+    }
+}
+
+/// Interpolated render transform, written by `InterpolationSystem`.
+///
+/// Kept separate from `T` so the physics pose is never overwritten; rendering should read `T`
+/// through `Interpolated<T>` instead of `T` directly when this system is in use.
+#[derive(Debug, Clone)]
+pub struct Interpolated<T>(pub T);
+
+impl<T: Send + Sync + 'static> Component for Interpolated<T> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Spherical/linear blend between two rotations of the same type, by a factor in `[0, 1]`.
+///
+/// `Rotation` alone isn't enough to interpolate, so this is implemented separately for the
+/// rotation types rhusics uses in 2D and 3D.
+pub trait Slerp<S> {
+    /// Interpolate between `self` and `other` by `amount`, in `[0, 1]`.
+    fn interpolate(&self, other: &Self, amount: S) -> Self;
+}
+
+impl<S: BaseFloat> Slerp<S> for Quaternion<S> {
+    fn interpolate(&self, other: &Self, amount: S) -> Self {
+        self.slerp(*other, amount)
+    }
+}
+
+impl<S: BaseFloat> Slerp<S> for Basis2<S> {
+    fn interpolate(&self, other: &Self, amount: S) -> Self {
+        let a = self.as_ref().x.y.atan2(self.as_ref().x.x);
+        let b = other.as_ref().x.y.atan2(other.as_ref().x.x);
+
+        // Wrap the delta into [-pi, pi] so interpolation always takes the shortest arc, instead
+        // of potentially spinning the long way around when `a` and `b` straddle +-pi.
+        let two_pi = S::pi() + S::pi();
+        let mut delta = b - a;
+        while delta > S::pi() {
+            delta = delta - two_pi;
+        }
+        while delta < -S::pi() {
+            delta = delta + two_pi;
+        }
+
+        Basis2::from_angle(cgmath::Rad(a + delta * amount))
+    }
+}
+
+/// Render interpolation system.
+///
+/// `CurrentFrameUpdateSystem` snaps the current `Pose` to its `NextFrame` value every physics
+/// step, which stutters when the physics step rate differs from the render frame rate. This
+/// system instead blends the current and next poses by [`InterpolationTime`](struct.InterpolationTime.html)
+/// and writes the result into a separate `Interpolated<T>` component, leaving the physics pose
+/// untouched.
+///
+/// Translation is blended with `lerp`, orientation with spherical linear interpolation
+/// (`slerp` for `Quaternion`, shortest-arc angle interpolation for `Basis2`). When an entity has no `NextFrame<T>`, its current
+/// pose is copied unchanged.
+///
+/// ### Type parameters:
+///
+/// - `P`: Positional quantity, usually `Point2` or `Point3`
+/// - `R`: Rotational quantity, usually `Basis2` or `Quaternion`
+/// - `T`: Transform type (`BodyPose2` or similar)
+///
+/// ### System function:
+///
+/// `fn(T, NextFrame<T>, InterpolationTime) -> Interpolated<T>`
+pub struct InterpolationSystem<P, R, T> {
+    m: marker::PhantomData<(P, R, T)>,
+}
+
+impl<P, R, T> InterpolationSystem<P, R, T>
+where
+    P: EuclideanSpace,
+    P::Diff: VectorSpace + InnerSpace + Debug,
+    P::Scalar: BaseFloat,
+    R: Rotation<P> + Slerp<P::Scalar>,
+    T: Pose<P, R>,
+{
+    /// Create system.
+    pub fn new() -> Self {
+        Self {
+            m: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, P, R, T> System<'a> for InterpolationSystem<P, R, T>
+where
+    P: EuclideanSpace + Send + Sync + 'static,
+    P::Diff: VectorSpace + InnerSpace + Debug + Send + Sync + 'static,
+    P::Scalar: BaseFloat + Send + Sync + 'static,
+    R: Rotation<P> + Slerp<P::Scalar> + Send + Sync + 'static,
+    T: Pose<P, R> + Component + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, InterpolationTime>,
+        ReadStorage<'a, T>,
+        ReadStorage<'a, NextFrame<T>>,
+        WriteStorage<'a, Interpolated<T>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time, poses, next_poses, mut interpolated) = data;
+        let alpha: P::Scalar = NumCast::from(time.alpha()).unwrap_or_else(P::Scalar::zero);
+
+        for (entity, pose, next) in (&entities, &poses, next_poses.maybe()).join() {
+            let value = match next {
+                Some(next) => {
+                    let position =
+                        pose.position() + (next.value.position() - pose.position()) * alpha;
+                    let rotation = pose.rotation().interpolate(&next.value.rotation(), alpha);
+                    T::new(position, rotation)
+                }
+                None => pose.clone(),
+            };
+            interpolated
+                .insert(entity, Interpolated(value))
+                .expect("Unreachable: entity is alive");
+        }
+    }
+}